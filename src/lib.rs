@@ -1,11 +1,11 @@
 //! Dependency resolver.
 //! We use a DFS algoritm for tree traversal and dependency cycle detection.
-//! In order to keep it simple, there is no complicated linked list with 
+//! In order to keep it simple, there is no complicated linked list with
 //! `smart` pointers involved, and we will detect cycles based on the id of
 //! each node in our graph.
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::Hash;
 
-#[allow(dead_code)]
 // This type help us to keep track of the vertices
 // we visit during cycle detection.
 type NodeIdTracker = HashSet<String>;
@@ -16,7 +16,553 @@ pub struct Node {
     pub deps: Vec<Node>,
 }
 
+// `Node` owns its children directly, so the compiler-generated `Drop` glue
+// would recurse one stack frame per level of the tree and overflow on a
+// chain thousands of nodes deep. Unlink children into a worklist first so
+// each one is dropped with an already-empty `deps`, keeping recursion depth
+// at 1 regardless of how deep the original tree was.
+impl Drop for Node {
+    fn drop(&mut self) {
+        let mut stack: Vec<Node> = std::mem::take(&mut self.deps);
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.deps);
+        }
+    }
+}
+
+/// The kind of a dependency edge. Real package graphs intentionally allow
+/// `Dev`/`Build` edges to form a cycle with the crate they depend on (e.g. a
+/// crate's dev-dependencies depending back on itself for its own tests), so
+/// only `Normal` edges are considered by cycle detection; `build_order`
+/// still walks every kind, since dev deps need to be installed too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+/// An id-keyed dependency graph backed by an adjacency map, as opposed to
+/// `Node`'s owned tree. A dependency shared by several parents is stored
+/// once, which lets `build_order` emit it exactly once instead of once per
+/// incoming edge.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    edges: HashMap<String, Vec<(String, EdgeKind)>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Graph {
+            edges: HashMap::new(),
+        }
+    }
+
+    /// Record that `from` has a normal dependency on `to`.
+    pub fn add_edge(&mut self, from: impl Into<String>, to: impl Into<String>) {
+        self.add_edge_kind(from, to, EdgeKind::Normal);
+    }
+
+    /// Record that `from` depends on `to` via an edge of the given `kind`.
+    pub fn add_edge_kind(&mut self, from: impl Into<String>, to: impl Into<String>, kind: EdgeKind) {
+        self.edges
+            .entry(from.into())
+            .or_default()
+            .push((to.into(), kind));
+    }
+
+    fn edges_of(&self, id: &str) -> &[(String, EdgeKind)] {
+        self.edges.get(id).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// Return every dependency reachable from `root` exactly once, in
+    /// dependency-first topological order, following edges of every kind: a
+    /// crate only appears after all of its dependencies have already been
+    /// emitted. Shorthand for `build_order_many(&[root])`.
+    pub fn build_order(&self, root: &str) -> Vec<String> {
+        self.build_order_many(&[root])
+    }
+
+    /// Same as `build_order`, but for a workspace with several top-level
+    /// members: every id reachable from any of `roots` is emitted exactly
+    /// once, dependency-first. The roots themselves are never emitted, even
+    /// when one root is reachable from another.
+    pub fn build_order_many(&self, roots: &[&str]) -> Vec<String> {
+        GraphAnalyzer::new(self).build_order_many(&Self::ids(roots))
+    }
+
+    /// Whether any cycle is reachable from `root` through `Normal`
+    /// dependencies (the cycle need not pass through `root` itself).
+    /// Shorthand for `has_cycle_many(&[root])`.
+    pub fn has_cycle(&self, root: &str) -> bool {
+        self.has_cycle_many(&[root])
+    }
+
+    /// Same as `has_cycle`, but checks every member of a workspace with
+    /// several top-level entry ids in one pass.
+    pub fn has_cycle_many(&self, roots: &[&str]) -> bool {
+        GraphAnalyzer::new(&NormalEdges(self)).has_cycle_many(&Self::ids(roots))
+    }
+
+    /// Return the sequence of ids forming the first cycle reachable from
+    /// `root` through `Normal` edges, ignoring `Dev`/`Build` back-edges.
+    /// Shorthand for `find_cycle_many(&[root])`.
+    pub fn find_cycle(&self, root: &str) -> Option<Vec<String>> {
+        self.find_cycle_many(&[root])
+    }
+
+    /// Same as `find_cycle`, but for a workspace with several top-level
+    /// entry ids: returns the first cycle found reachable from any of
+    /// `roots`.
+    pub fn find_cycle_many(&self, roots: &[&str]) -> Option<Vec<String>> {
+        GraphAnalyzer::new(&NormalEdges(self)).find_cycle_many(&Self::ids(roots))
+    }
+
+    /// Return every strongly connected component of size > 1 (plus any
+    /// self-loop) reachable from `root` through `Normal` edges — i.e. every
+    /// independent cycle, ignoring `Dev`/`Build` back-edges. Shorthand for
+    /// `find_all_cycles_many(&[root])`.
+    pub fn find_all_cycles(&self, root: &str) -> Vec<Vec<String>> {
+        self.find_all_cycles_many(&[root])
+    }
+
+    /// Same as `find_all_cycles`, but for a workspace with several
+    /// top-level entry ids: returns every independent cycle reachable from
+    /// any of `roots`.
+    pub fn find_all_cycles_many(&self, roots: &[&str]) -> Vec<Vec<String>> {
+        GraphAnalyzer::new(&NormalEdges(self)).find_all_cycles_many(&Self::ids(roots))
+    }
+
+    fn ids(roots: &[&str]) -> Vec<String> {
+        roots.iter().map(|r| r.to_string()).collect()
+    }
+
+    /// Serialize the full edge set to Graphviz DOT format, e.g.
+    /// `digraph { "a"; "aa"; "a" -> "aa"; }` with one statement per line
+    /// (every node first, then every edge), mirroring rustc's dependency-graph
+    /// dumping pass. Nodes and edges are each emitted exactly once, sorted
+    /// so the output is deterministic and safe to snapshot-test. Edges
+    /// that participate in a cycle anywhere in the graph (per
+    /// `find_all_cycles_many`, scanning every node as a potential entry
+    /// point) are drawn in red so cycles are easy to spot by eye in a
+    /// rendered graph, even ones in a part of the graph no single `root`
+    /// would reach.
+    pub fn to_dot(&self) -> String {
+        let mut nodes: BTreeSet<&str> = BTreeSet::new();
+        let mut edges: BTreeSet<(&str, &str)> = BTreeSet::new();
+
+        for (from, tos) in &self.edges {
+            nodes.insert(from.as_str());
+            for (to, _) in tos {
+                nodes.insert(to.as_str());
+                edges.insert((from.as_str(), to.as_str()));
+            }
+        }
+
+        let cycle_edges = self.cycle_edges(&nodes);
+
+        let mut dot = String::from("digraph {\n");
+        for node in &nodes {
+            dot.push_str(&format!("    \"{}\";\n", node));
+        }
+        for (from, to) in &edges {
+            if cycle_edges.contains(&(from.to_string(), to.to_string())) {
+                dot.push_str(&format!("    \"{}\" -> \"{}\" [color=red];\n", from, to));
+            } else {
+                dot.push_str(&format!("    \"{}\" -> \"{}\";\n", from, to));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Edges whose endpoints lie in the same strongly connected component,
+    // i.e. the edges that actually form a cycle. Scans every one of
+    // `all_nodes` as a potential entry point, rather than a single `root`,
+    // so a cycle in a disconnected part of the graph is still found.
+    // `to_dot` highlights these.
+    fn cycle_edges(&self, all_nodes: &BTreeSet<&str>) -> HashSet<(String, String)> {
+        let roots: Vec<String> = all_nodes.iter().map(|id| id.to_string()).collect();
+        let sccs = GraphAnalyzer::new(&NormalEdges(self)).find_all_cycles_many(&roots);
+
+        let mut component_of: HashMap<&str, usize> = HashMap::new();
+        for (i, component) in sccs.iter().enumerate() {
+            for id in component {
+                component_of.insert(id.as_str(), i);
+            }
+        }
+
+        let mut highlighted = HashSet::new();
+        for (from, tos) in &self.edges {
+            let from_comp = match component_of.get(from.as_str()) {
+                Some(c) => c,
+                None => continue,
+            };
+            for (to, kind) in tos {
+                if *kind == EdgeKind::Normal && component_of.get(to.as_str()) == Some(from_comp) {
+                    highlighted.insert((from.clone(), to.clone()));
+                }
+            }
+        }
+        highlighted
+    }
+}
+
+/// A reusable abstraction over any id-keyed dependency structure (module
+/// graphs, task graphs, etc.) so that callers don't have to copy their data
+/// into `Node` just to get cycle/order analysis.
+pub trait DepGraph {
+    type Id: Eq + Hash + Clone + Ord;
+
+    /// Return the ids `id` directly depends on.
+    fn deps_of(&self, id: &Self::Id) -> Vec<Self::Id>;
+}
+
+impl DepGraph for Graph {
+    type Id = String;
+
+    fn deps_of(&self, id: &String) -> Vec<String> {
+        self.edges_of(id).iter().map(|(dep, _)| dep.clone()).collect()
+    }
+}
+
+// A view over a `Graph` that exposes only its `Normal` edges, so cycle
+// detection never recurses into a back-edge that only exists through a
+// `Dev`/`Build` dependency.
+struct NormalEdges<'g>(&'g Graph);
+
+impl<'g> DepGraph for NormalEdges<'g> {
+    type Id = String;
+
+    fn deps_of(&self, id: &String) -> Vec<String> {
+        self.0
+            .edges_of(id)
+            .iter()
+            .filter(|(_, kind)| *kind == EdgeKind::Normal)
+            .map(|(dep, _)| dep.clone())
+            .collect()
+    }
+}
+
+impl DepGraph for Node {
+    type Id = String;
+
+    fn deps_of(&self, id: &String) -> Vec<String> {
+        self.find(id)
+            .map(|node| node.deps.iter().map(|dep| dep.id.clone()).collect())
+            .unwrap_or_default()
+    }
+}
+
+// A single frame of an explicit-stack DFS: the id being visited, the deps
+// fetched for it, and the index of the next dep still to visit. Kept on a
+// heap-allocated `Vec` instead of the call stack so a chain thousands of
+// ids deep cannot overflow it.
+type DfsStack<Id> = Vec<(Id, Vec<Id>, usize)>;
+
+/// Runs DFS-based traversal (deduplicated build order, cycle detection) over
+/// any `DepGraph`, without ever copying the caller's graph into `Node`.
+pub struct GraphAnalyzer<'g, G: DepGraph> {
+    graph: &'g G,
+}
+
+impl<'g, G: DepGraph> GraphAnalyzer<'g, G> {
+    pub fn new(graph: &'g G) -> Self {
+        GraphAnalyzer { graph }
+    }
+
+    /// Return every id reachable from `root` exactly once, in
+    /// dependency-first topological order. Shorthand for
+    /// `build_order_many(&[root.clone()])`.
+    pub fn build_order(&self, root: &G::Id) -> Vec<G::Id> {
+        self.build_order_many(std::slice::from_ref(root))
+    }
+
+    /// Same as `build_order`, but for one or more entry ids (e.g. a
+    /// workspace with several top-level members): every id reachable from
+    /// any of `roots` is emitted exactly once, dependency-first. The roots
+    /// themselves are never emitted, even when one root is reachable from
+    /// another.
+    pub fn build_order_many(&self, roots: &[G::Id]) -> Vec<G::Id> {
+        let mut visited = HashSet::new();
+        let mut order = Vec::new();
+
+        // A root is a top-level entry point, not a "dependency to build",
+        // so it's marked visited up front and never itself pushed to
+        // `order` — only the roots' transitive deps are.
+        for root in roots {
+            visited.insert(root.clone());
+        }
+        for root in roots {
+            for dep in self.graph.deps_of(root) {
+                self.build_order_iter(&dep, &mut visited, &mut order);
+            }
+        }
+
+        order
+    }
+
+    // Iterative post-order DFS guarded by `visited`, kept on an explicit
+    // `Vec` of `(id, deps, index of next dep to visit)` frames instead of
+    // the call stack so a chain thousands of ids deep cannot overflow it.
+    // A node is emitted once all of its deps have been pushed and popped.
+    fn build_order_iter(&self, start: &G::Id, visited: &mut HashSet<G::Id>, order: &mut Vec<G::Id>) {
+        if visited.contains(start) {
+            return;
+        }
+        visited.insert(start.clone());
+        let mut stack: DfsStack<G::Id> = vec![(start.clone(), self.graph.deps_of(start), 0)];
+
+        while let Some((id, deps, idx)) = stack.pop() {
+            if idx < deps.len() {
+                let dep = deps[idx].clone();
+                stack.push((id, deps, idx + 1));
+
+                if !visited.contains(&dep) {
+                    visited.insert(dep.clone());
+                    let dep_deps = self.graph.deps_of(&dep);
+                    stack.push((dep, dep_deps, 0));
+                }
+            } else {
+                order.push(id);
+            }
+        }
+    }
+
+    /// Whether any cycle is reachable from `root` through its dependencies
+    /// (the cycle need not pass through `root` itself). Shorthand for
+    /// `has_cycle_many(&[root.clone()])`.
+    pub fn has_cycle(&self, root: &G::Id) -> bool {
+        self.has_cycle_many(std::slice::from_ref(root))
+    }
+
+    /// Same as `has_cycle`, but checks every one of `roots` in one pass.
+    pub fn has_cycle_many(&self, roots: &[G::Id]) -> bool {
+        self.find_cycle_many(roots).is_some()
+    }
+
+    /// Return the sequence of ids forming the first cycle reachable from
+    /// `root`, or `None` if none exists. Shorthand for
+    /// `find_cycle_many(&[root.clone()])`.
+    pub fn find_cycle(&self, root: &G::Id) -> Option<Vec<G::Id>> {
+        self.find_cycle_many(std::slice::from_ref(root))
+    }
+
+    /// Same as `find_cycle`, but for one or more entry ids: returns the
+    /// first cycle found reachable from any of `roots`. Same three-color
+    /// DFS as `Node::find_cycle`, generalized over any `DepGraph` and kept
+    /// on an explicit stack so it cannot overflow on a deep chain.
+    pub fn find_cycle_many(&self, roots: &[G::Id]) -> Option<Vec<G::Id>> {
+        let mut gray = HashSet::new();
+        let mut black = HashSet::new();
+
+        for root in roots {
+            let mut path = Vec::new();
+            if let Some(cycle) = self.find_cycle_iter(root, &mut gray, &mut black, &mut path) {
+                return Some(cycle);
+            }
+        }
+
+        None
+    }
+
+    // Iterative three-color DFS (white / gray / black), kept on an
+    // explicit `Vec` of `(id, deps, index of next dep to visit)` frames
+    // instead of the call stack. `gray` holds the ids currently on `path`,
+    // `black` holds ids fully processed and already known to be acyclic.
+    // When a back-edge into a gray id `x` is hit, `path` is sliced from the
+    // first occurrence of `x` onward and the loop is closed by appending
+    // `x` again.
+    fn find_cycle_iter(
+        &self,
+        root: &G::Id,
+        gray: &mut HashSet<G::Id>,
+        black: &mut HashSet<G::Id>,
+        path: &mut Vec<G::Id>,
+    ) -> Option<Vec<G::Id>> {
+        if black.contains(root) {
+            return None;
+        }
+        if gray.contains(root) {
+            let start = path.iter().position(|x| x == root)?;
+            let mut cycle = path[start..].to_vec();
+            cycle.push(root.clone());
+            return Some(cycle);
+        }
+
+        gray.insert(root.clone());
+        path.push(root.clone());
+        let mut stack: DfsStack<G::Id> = vec![(root.clone(), self.graph.deps_of(root), 0)];
+
+        while let Some((id, deps, idx)) = stack.pop() {
+            if idx < deps.len() {
+                let dep = deps[idx].clone();
+                stack.push((id, deps, idx + 1));
+
+                if black.contains(&dep) {
+                    continue;
+                }
+                if gray.contains(&dep) {
+                    let start = path.iter().position(|x| x == &dep)?;
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(dep);
+                    return Some(cycle);
+                }
+
+                gray.insert(dep.clone());
+                path.push(dep.clone());
+                let dep_deps = self.graph.deps_of(&dep);
+                stack.push((dep, dep_deps, 0));
+            } else {
+                path.pop();
+                gray.remove(&id);
+                black.insert(id);
+            }
+        }
+
+        None
+    }
+
+    /// Compute strongly connected components reachable from `root` using
+    /// Tarjan's algorithm, and return every component of size > 1 (plus any
+    /// self-loop). Shorthand for `find_all_cycles_many(&[root.clone()])`.
+    pub fn find_all_cycles(&self, root: &G::Id) -> Vec<Vec<G::Id>> {
+        self.find_all_cycles_many(std::slice::from_ref(root))
+    }
+
+    /// Same as `find_all_cycles`, but for one or more entry ids (e.g. a
+    /// workspace with several top-level members): returns every
+    /// independent cycle reachable from any of `roots`, each reported once.
+    pub fn find_all_cycles_many(&self, roots: &[G::Id]) -> Vec<Vec<G::Id>> {
+        let mut state = TarjanState::default();
+
+        for root in roots {
+            if !state.index.contains_key(root) {
+                self.tarjan_visit(root, &mut state);
+            }
+        }
+
+        state
+            .sccs
+            .into_iter()
+            .filter(|component| component.len() > 1 || self.has_self_loop(&component[0]))
+            .collect()
+    }
+
+    fn has_self_loop(&self, id: &G::Id) -> bool {
+        self.graph.deps_of(id).iter().any(|dep| dep == id)
+    }
+
+    // Tarjan's SCC algorithm, run iteratively so a chain thousands of ids
+    // deep cannot overflow the call stack. Each frame on the explicit
+    // `Vec` stack is `(id, deps, index of next dep to visit)`; `tarjan_push`
+    // does the bookkeeping the recursive version did on entering a node
+    // (assign `index`/`lowlink`, push onto `state.stack`, mark on-stack).
+    // When a frame has no deps left to visit, it has "returned": we
+    // propagate its `lowlink` into whichever frame called it (now on top
+    // of the stack) exactly as the recursive version did right after its
+    // recursive call, then close the SCC if this id roots one.
+    fn tarjan_visit(&self, root: &G::Id, state: &mut TarjanState<G::Id>) {
+        if state.index.contains_key(root) {
+            return;
+        }
+
+        let mut stack: DfsStack<G::Id> = Vec::new();
+        self.tarjan_push(root, state, &mut stack);
+
+        while let Some((id, deps, idx)) = stack.pop() {
+            if idx < deps.len() {
+                let dep = deps[idx].clone();
+                stack.push((id.clone(), deps, idx + 1));
+
+                if !state.index.contains_key(&dep) {
+                    self.tarjan_push(&dep, state, &mut stack);
+                } else if state.on_stack.contains(&dep) {
+                    let dep_index = state.index[&dep];
+                    let lowlink = state.lowlink.get_mut(&id).unwrap();
+                    *lowlink = (*lowlink).min(dep_index);
+                }
+            } else {
+                if let Some((parent_id, _, _)) = stack.last() {
+                    let child_lowlink = state.lowlink[&id];
+                    let parent_lowlink = state.lowlink.get_mut(parent_id).unwrap();
+                    *parent_lowlink = (*parent_lowlink).min(child_lowlink);
+                }
+
+                if state.index[&id] == state.lowlink[&id] {
+                    let mut component = Vec::new();
+                    loop {
+                        let w = state.stack.pop().unwrap();
+                        state.on_stack.remove(&w);
+                        component.push(w.clone());
+                        if w == id {
+                            break;
+                        }
+                    }
+                    state.sccs.push(component);
+                }
+            }
+        }
+    }
+
+    // Assign a fresh Tarjan `index`/`lowlink` to `id`, push it onto the
+    // component stack, and push its traversal frame onto the explicit
+    // call stack — the bookkeeping a recursive Tarjan does on entering a
+    // node, before looping over its successors.
+    fn tarjan_push(&self, id: &G::Id, state: &mut TarjanState<G::Id>, stack: &mut DfsStack<G::Id>) {
+        state.index.insert(id.clone(), state.next_index);
+        state.lowlink.insert(id.clone(), state.next_index);
+        state.next_index += 1;
+        state.stack.push(id.clone());
+        state.on_stack.insert(id.clone());
+
+        let deps = self.graph.deps_of(id);
+        stack.push((id.clone(), deps, 0));
+    }
+}
+
+// Scratch state threaded through `GraphAnalyzer::tarjan_visit`.
+struct TarjanState<Id> {
+    index: HashMap<Id, usize>,
+    lowlink: HashMap<Id, usize>,
+    on_stack: HashSet<Id>,
+    stack: Vec<Id>,
+    next_index: usize,
+    sccs: Vec<Vec<Id>>,
+}
+
+impl<Id> Default for TarjanState<Id> {
+    fn default() -> Self {
+        TarjanState {
+            index: HashMap::new(),
+            lowlink: HashMap::new(),
+            on_stack: HashSet::new(),
+            stack: Vec::new(),
+            next_index: 0,
+            sccs: Vec::new(),
+        }
+    }
+}
+
 impl Node {
+    // Find the node with the given id anywhere in this tree. Iterative,
+    // kept on an explicit `Vec` stack of node references instead of the
+    // call stack: `Node`'s `DepGraph::deps_of` calls this on every id
+    // `GraphAnalyzer` visits, so a recursive `find` would add its own
+    // depth to an already deep traversal on a long dependency chain.
+    fn find(&self, id: &str) -> Option<&Node> {
+        let mut stack: Vec<&Node> = vec![self];
+
+        while let Some(node) = stack.pop() {
+            if node.id == id {
+                return Some(node);
+            }
+            stack.extend(node.deps.iter());
+        }
+
+        None
+    }
+
     // Return the list of all the dependencies to build.
     pub fn get_dependancy_list(&self) -> Vec<String> {
         if let Some(dependancy_list) = self.walk() {
@@ -26,56 +572,156 @@ impl Node {
         }
     }
 
-    // Walk through the graph using DFS algorithm.
+    // Walk through the graph using an iterative post-order DFS: a native
+    // recursive descent would overflow the call stack on a chain thousands
+    // of nodes deep, so we keep the frames on an explicit, heap-allocated
+    // `Vec` instead. Each frame is a `(node, index of next child to visit)`
+    // pair; a node is emitted once all of its children have been pushed
+    // and popped.
     fn walk(&self) -> Option<Vec<String>> {
         if self.deps.is_empty() {
-            None
-        } else {
-            let mut stack: Vec<String> = vec![];
-            for dep in &self.deps {
-                if let Some(dep) = dep.walk() {
-                    stack.append(&mut dep.clone());
-                }
-                stack.push(dep.id.clone());
+            return None;
+        }
+
+        let mut order = Vec::new();
+        let mut stack: Vec<(&Node, usize)> = vec![(self, 0)];
+
+        while let Some((node, idx)) = stack.pop() {
+            if idx < node.deps.len() {
+                stack.push((node, idx + 1));
+                stack.push((&node.deps[idx], 0));
+            } else if !std::ptr::eq(node, self) {
+                order.push(node.id.clone());
             }
-            Some(stack)
         }
+
+        Some(order)
     }
 
     // Detect if a graph is a DAG or not.
     #[allow(dead_code)]
     fn has_cycle(&self) -> bool {
-        let mut visited = NodeIdTracker::new();
+        let mut gray = NodeIdTracker::new();
+        let mut black = NodeIdTracker::new();
 
-        if let Some(v) = self.detect_cycles(&mut visited) {
-            v
-        } else {
-            false
-        }
+        self.detect_cycles(&mut gray, &mut black)
     }
 
-    // Walk through the graph and stopping at the first cycle it encounters.
+    // Walk through the graph using an iterative three-color DFS (white /
+    // gray / black), kept on an explicit `Vec` of `(node, index of next
+    // child to visit)` frames instead of the call stack so a chain
+    // thousands of nodes deep cannot overflow it. `gray` holds the ids
+    // currently on the active path, `black` holds ids fully processed and
+    // already known to be acyclic: a node reachable through two different
+    // branches of a DAG (a diamond dependency) hits `black` and is skipped
+    // without recursing, while a back-edge into `gray` is a genuine cycle.
     #[allow(dead_code)]
-    fn detect_cycles(&self, visited: &mut NodeIdTracker) -> Option<bool> {
-        // We already visited this node, meaning we encountered a cycle
-        // in the graph.
-        if visited.contains(&self.id) {
-            return Some(true);
+    fn detect_cycles(&self, gray: &mut NodeIdTracker, black: &mut NodeIdTracker) -> bool {
+        if black.contains(&self.id) {
+            return false;
+        }
+        if gray.contains(&self.id) {
+            return true;
         }
 
-        // Keeping track of the nodes we are visiting.
-        visited.insert(self.id.clone());
+        gray.insert(self.id.clone());
+        let mut stack: Vec<(&Node, usize)> = vec![(self, 0)];
 
-        if self.deps.is_empty() {
+        while let Some((node, idx)) = stack.pop() {
+            if idx < node.deps.len() {
+                stack.push((node, idx + 1));
+
+                let dep = &node.deps[idx];
+                if black.contains(&dep.id) {
+                    continue;
+                }
+                if gray.contains(&dep.id) {
+                    return true;
+                }
+
+                gray.insert(dep.id.clone());
+                stack.push((dep, 0));
+            } else {
+                gray.remove(&node.id);
+                black.insert(node.id.clone());
+            }
+        }
+
+        false
+    }
+
+    /// Return the sequence of node ids forming the first cycle encountered,
+    /// e.g. `["b", "bb", "bba", "b"]`, or `None` if the graph is acyclic.
+    /// Callers can use this to render an actionable error message naming
+    /// exactly which packages form the cycle.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        let mut gray = NodeIdTracker::new();
+        let mut black = NodeIdTracker::new();
+        let mut path: Vec<String> = vec![];
+
+        self.find_cycle_iter(&mut gray, &mut black, &mut path)
+    }
+
+    // Same three-color DFS as `detect_cycles`, but additionally keeps an
+    // ordered `path` of the ids currently on the active path, kept on an
+    // explicit `Vec` of `(node, index of next child to visit)` frames
+    // instead of the call stack so a chain thousands of nodes deep cannot
+    // overflow it. When a back-edge into a gray node `x` is hit, we slice
+    // `path` from the first occurrence of `x` onward and close the loop by
+    // appending `x` again.
+    fn find_cycle_iter(
+        &self,
+        gray: &mut NodeIdTracker,
+        black: &mut NodeIdTracker,
+        path: &mut Vec<String>,
+    ) -> Option<Vec<String>> {
+        if black.contains(&self.id) {
             return None;
-        } else {
-            for dep in &self.deps {
-                if let Some(true) = dep.detect_cycles(visited) {
-                    return Some(true);
+        }
+        if gray.contains(&self.id) {
+            let start = path.iter().position(|id| id == &self.id)?;
+            let mut cycle = path[start..].to_vec();
+            cycle.push(self.id.clone());
+            return Some(cycle);
+        }
+
+        gray.insert(self.id.clone());
+        path.push(self.id.clone());
+        let mut stack: Vec<(&Node, usize)> = vec![(self, 0)];
+
+        while let Some((node, idx)) = stack.pop() {
+            if idx < node.deps.len() {
+                stack.push((node, idx + 1));
+
+                let dep = &node.deps[idx];
+                if black.contains(&dep.id) {
+                    continue;
+                }
+                if gray.contains(&dep.id) {
+                    let start = path.iter().position(|id| id == &dep.id)?;
+                    let mut cycle = path[start..].to_vec();
+                    cycle.push(dep.id.clone());
+                    return Some(cycle);
                 }
+
+                gray.insert(dep.id.clone());
+                path.push(dep.id.clone());
+                stack.push((dep, 0));
+            } else {
+                path.pop();
+                gray.remove(&node.id);
+                black.insert(node.id.clone());
             }
         }
-        Some(false)
+
+        None
+    }
+
+    /// Return every strongly connected component of size > 1 (plus any
+    /// self-loop) in this graph — i.e. every independent cycle, not just
+    /// the first one `find_cycle` stops at.
+    pub fn find_all_cycles(&self) -> Vec<Vec<String>> {
+        GraphAnalyzer::new(self).find_all_cycles(&self.id)
     }
 }
 
@@ -210,6 +856,35 @@ mod test_super {
         }
     }
 
+    /// A DAG where the same dependency id is reachable through two
+    /// different branches (a diamond), but the graph is still acyclic:
+    ///              (Mylib)
+    ///           /          \
+    ///         (a)          (b)
+    ///           \          /
+    ///           (shared)  (shared)
+    fn mock_diamond() -> Node {
+        Node {
+            id: "MyLib".to_string(),
+            deps: vec![
+                Node {
+                    id: "a".into(),
+                    deps: vec![Node {
+                        id: "shared".into(),
+                        deps: vec![],
+                    }],
+                },
+                Node {
+                    id: "b".into(),
+                    deps: vec![Node {
+                        id: "shared".into(),
+                        deps: vec![],
+                    }],
+                },
+            ],
+        }
+    }
+
     #[test]
     fn test_dag() {
         let graph = mock_dag();
@@ -231,4 +906,273 @@ mod test_super {
         let graph = mock_cycle();
         assert_eq!(graph.has_cycle(), true);
     }
+
+    #[test]
+    fn test_detect_cycle_diamond() {
+        let graph = mock_diamond();
+        assert!(!graph.has_cycle());
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        let graph = mock_dag();
+        assert_eq!(graph.find_cycle(), None);
+
+        let graph = mock_cycle();
+        assert_eq!(
+            graph.find_cycle(),
+            Some(vec!["b".to_string(), "bb".to_string(), "bba".to_string(), "b".to_string()])
+        );
+    }
+
+    /// Build a `Graph` where "shared" is depended on by both "a" and "b":
+    ///      (MyLib)
+    ///      /     \
+    ///   (a)       (b)
+    ///      \       /
+    ///      (shared)
+    fn mock_shared_graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.add_edge("MyLib", "a");
+        graph.add_edge("MyLib", "b");
+        graph.add_edge("a", "shared");
+        graph.add_edge("b", "shared");
+        graph
+    }
+
+    #[test]
+    fn test_build_order_dedups_shared_dependency() {
+        let graph = mock_shared_graph();
+        let order = graph.build_order("MyLib");
+
+        assert_eq!(order, vec!["shared", "a", "b"]);
+    }
+
+    #[test]
+    fn test_dev_edge_cycle_is_exempt_from_cycle_detection() {
+        let mut graph = Graph::new();
+        graph.add_edge("app", "lib");
+        graph.add_edge_kind("lib", "app", EdgeKind::Dev);
+
+        assert!(!graph.has_cycle("app"));
+        assert_eq!(graph.find_cycle("app"), None);
+        assert_eq!(graph.find_all_cycles("app"), Vec::<Vec<String>>::new());
+
+        // The full edge set (normal + dev) is still walked for build_order.
+        assert_eq!(graph.build_order("app"), vec!["lib"]);
+    }
+
+    #[test]
+    fn test_normal_edge_cycle_is_still_detected() {
+        let mut graph = Graph::new();
+        graph.add_edge("app", "lib");
+        graph.add_edge("lib", "app");
+
+        assert!(graph.has_cycle("app"));
+    }
+
+    #[test]
+    fn test_find_all_cycles_single_cycle() {
+        let graph = mock_cycle();
+        let mut cycles = graph.find_all_cycles();
+        assert_eq!(cycles.len(), 1);
+
+        let mut component = cycles.pop().unwrap();
+        component.sort();
+        assert_eq!(component, vec!["b".to_string(), "bb".to_string(), "bba".to_string()]);
+    }
+
+    #[test]
+    fn test_find_all_cycles_multiple_independent_cycles() {
+        let mut graph = Graph::new();
+        graph.add_edge("root", "x");
+        graph.add_edge("root", "y");
+        graph.add_edge("x", "x2");
+        graph.add_edge("x2", "x");
+        graph.add_edge("y", "y2");
+        graph.add_edge("y2", "y");
+
+        let mut cycles = graph.find_all_cycles("root");
+        for component in cycles.iter_mut() {
+            component.sort();
+        }
+        cycles.sort();
+
+        assert_eq!(
+            cycles,
+            vec![
+                vec!["x".to_string(), "x2".to_string()],
+                vec!["y".to_string(), "y2".to_string()],
+            ]
+        );
+    }
+
+    /// A workspace with two top-level members, "appA" and "appB", that
+    /// both depend on a shared "lib", plus a cycle only reachable from
+    /// "appB".
+    #[test]
+    fn test_multi_root_workspace() {
+        let mut graph = Graph::new();
+        graph.add_edge("appA", "lib");
+        graph.add_edge("appB", "lib");
+        graph.add_edge("appB", "x");
+        graph.add_edge("x", "y");
+        graph.add_edge("y", "x");
+
+        let roots = ["appA", "appB"];
+
+        // "lib" is reachable from both roots but only emitted once, and
+        // neither root itself is treated as a dependency to build.
+        assert_eq!(graph.build_order_many(&roots), vec!["lib", "y", "x"]);
+
+        // A cycle reachable only from "appB" is still found when scanning
+        // every root in one pass.
+        assert!(graph.has_cycle_many(&roots));
+        let mut cycle = graph.find_cycle_many(&roots).unwrap();
+        cycle.sort();
+        assert_eq!(cycle, vec!["x".to_string(), "x".to_string(), "y".to_string()]);
+
+        let mut cycles = graph.find_all_cycles_many(&roots);
+        for component in cycles.iter_mut() {
+            component.sort();
+        }
+        assert_eq!(cycles, vec![vec!["x".to_string(), "y".to_string()]]);
+    }
+
+    #[test]
+    fn test_graph_analyzer_works_on_node() {
+        let graph = mock_dag();
+        let analyzer = GraphAnalyzer::new(&graph);
+
+        assert_eq!(
+            analyzer.build_order(&"MyLib".to_string()),
+            vec!["aa", "ab", "a", "baa", "ba", "bba", "bb", "bc", "b", "ca", "c"]
+        );
+
+        let graph = mock_cycle();
+        let analyzer = GraphAnalyzer::new(&graph);
+        assert!(analyzer.find_cycle(&"MyLib".to_string()).is_some());
+    }
+
+    // Build a linear chain of `depth` nodes iteratively (not recursively,
+    // so constructing the fixture itself doesn't blow the stack): each node
+    // depends on exactly one other, wrapping the previous one.
+    fn mock_chain(depth: usize) -> Node {
+        let mut node = Node {
+            id: "0".to_string(),
+            deps: vec![],
+        };
+        for i in 1..depth {
+            node = Node {
+                id: i.to_string(),
+                deps: vec![node],
+            };
+        }
+        node
+    }
+
+    #[test]
+    fn test_deep_chain_does_not_overflow_stack() {
+        let depth = 5_000;
+        let chain = mock_chain(depth);
+
+        assert_eq!(chain.get_dependancy_list().len(), depth - 1);
+        assert!(!chain.has_cycle());
+    }
+
+    // `Node::find` is O(depth) per lookup, and `GraphAnalyzer` calls it
+    // once per id visited, so a very deep chain through `Node::find_all_cycles`
+    // costs O(depth^2); keep this shallower than the `Graph`-backed deep
+    // chain test below (whose `deps_of` is a single O(1) map lookup) so the
+    // test still proves the stack-safety fix without running for minutes.
+    #[test]
+    fn test_node_find_all_cycles_does_not_overflow_stack() {
+        let depth = 10_000;
+        let chain = mock_chain(depth);
+
+        assert_eq!(chain.find_all_cycles(), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn test_node_find_cycle_does_not_overflow_stack() {
+        let depth = 50_000;
+        let chain = mock_chain(depth);
+
+        assert_eq!(chain.find_cycle(), None);
+    }
+
+    /// Build a `Graph` that's a single linear chain `"0" -> "1" -> ... ->
+    /// "depth - 1"`, exercising `Graph`/`GraphAnalyzer`'s traversals (as
+    /// opposed to `Node`'s owned-tree ones) on a chain deep enough to
+    /// overflow a recursive DFS.
+    fn mock_chain_graph(depth: usize) -> Graph {
+        let mut graph = Graph::new();
+        for i in 0..depth - 1 {
+            graph.add_edge(i.to_string(), (i + 1).to_string());
+        }
+        graph
+    }
+
+    #[test]
+    fn test_graph_deep_chain_does_not_overflow_stack() {
+        let depth = 50_000;
+        let graph = mock_chain_graph(depth);
+
+        assert_eq!(graph.build_order("0").len(), depth - 1);
+        assert!(!graph.has_cycle("0"));
+        assert_eq!(graph.find_all_cycles("0"), Vec::<Vec<String>>::new());
+    }
+
+    #[test]
+    fn test_to_dot_dedups_and_is_deterministic() {
+        let mut graph = mock_shared_graph();
+        // Re-adding an edge that already exists must not duplicate it.
+        graph.add_edge("MyLib", "a");
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph {\n    \"MyLib\";\n    \"a\";\n    \"b\";\n    \"shared\";\n    \"MyLib\" -> \"a\";\n    \"MyLib\" -> \"b\";\n    \"a\" -> \"shared\";\n    \"b\" -> \"shared\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_highlights_cycle_edges() {
+        let mut graph = Graph::new();
+        graph.add_edge("app", "lib");
+        graph.add_edge("lib", "app");
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph {\n    \"app\";\n    \"lib\";\n    \"app\" -> \"lib\" [color=red];\n    \"lib\" -> \"app\" [color=red];\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_does_not_highlight_dev_edge_cycle() {
+        let mut graph = Graph::new();
+        graph.add_edge("app", "lib");
+        graph.add_edge_kind("lib", "app", EdgeKind::Dev);
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph {\n    \"app\";\n    \"lib\";\n    \"app\" -> \"lib\";\n    \"lib\" -> \"app\";\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_highlights_cycle_disconnected_from_any_single_root() {
+        // "MyLib" depends on "a" (acyclic), while a completely separate
+        // "x" <-> "y" cycle sits in a disconnected part of the same graph.
+        // A DOT dump of the whole graph must still flag it in red, even
+        // though no single root reaches both parts.
+        let mut graph = Graph::new();
+        graph.add_edge("MyLib", "a");
+        graph.add_edge("x", "y");
+        graph.add_edge("y", "x");
+
+        assert_eq!(
+            graph.to_dot(),
+            "digraph {\n    \"MyLib\";\n    \"a\";\n    \"x\";\n    \"y\";\n    \"MyLib\" -> \"a\";\n    \"x\" -> \"y\" [color=red];\n    \"y\" -> \"x\" [color=red];\n}\n"
+        );
+    }
 }